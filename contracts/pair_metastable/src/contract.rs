@@ -0,0 +1,1364 @@
+use cosmwasm_std::{
+    ensure, entry_point, to_json_binary, Addr, Binary, Decimal, Decimal256, Deps, DepsMut, Env,
+    MessageInfo, Response, StdError, StdResult, Uint128, Uint256,
+};
+
+use astroport::asset::{Asset, AssetInfo, PairInfo};
+use cosmwasm_std::{CosmosMsg, Reply, SubMsg, SubMsgResult, WasmMsg};
+use astroport::pair_metastable::{
+    ConcentratedPoolParams, ConfigResponse, CumulativePricesResponse, Cw20HookMsg, ExecuteMsg,
+    InstantiateMsg, LidoPoolParams, PoolResponse, QueryMsg, ReverseSimulationResponse,
+    SimulationResponse, DEFAULT_SLIPPAGE, MAX_ALLOWED_SLIPPAGE,
+};
+use std::str::FromStr;
+use astroport::factory::PairType;
+use cw20::{Cw20ReceiveMsg, MinterResponse};
+
+use crate::error::ContractError;
+use crate::math::{compute_d, compute_y, scale, unscale};
+use crate::state::{Config, ConcentratedState, CONFIG};
+
+/// A `reply` id for the submessage that instantiates the LP token contract.
+const INSTANTIATE_TOKEN_REPLY_ID: u64 = 1;
+
+/// The default maximum commission fraction a swap may route to a referral address (1%).
+const DEFAULT_MAX_REFERRAL_COMMISSION: (u128, u128) = (1, 100);
+
+/// The default amplification coefficient for the metastable invariant.
+const AMP: u128 = 100;
+
+/// The maximum number of assets a single pool may hold (e.g. stLUNA/bLUNA/LUNA baskets).
+pub const MAX_ASSETS: usize = 5;
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    ensure!(
+        msg.asset_infos.len() >= 2 && msg.asset_infos.len() <= MAX_ASSETS,
+        ContractError::InvalidNumberOfAssets {
+            max: MAX_ASSETS,
+            provided: msg.asset_infos.len(),
+        }
+    );
+
+    let params: LidoPoolParams = cosmwasm_std::from_json(&msg.init_params)?;
+    let factory_addr = deps.api.addr_validate(&msg.factory_addr)?;
+    let hub_address = deps.api.addr_validate(&params.hub_address)?;
+    let stluna_address = deps.api.addr_validate(&params.stluna_addr)?;
+    let bluna_address = deps.api.addr_validate(&params.bluna_addr)?;
+
+    let config = Config {
+        pair_info: PairInfo {
+            asset_infos: msg.asset_infos.clone(),
+            contract_addr: env.contract.address.clone(),
+            liquidity_token: Addr::unchecked(""),
+            pair_type: PairType::Custom("metastable".to_string()),
+        },
+        factory_addr,
+        owner: info.sender,
+        block_time_last: 0,
+        hub_address,
+        stluna_address,
+        bluna_address,
+        ma_half_time: params.ma_half_time,
+        // Stored on the first swap or liquidity provision; zero forces the first hub query.
+        target_rate: Decimal::zero(),
+        target_rate_last: 0,
+        max_referral_commission: Decimal::from_ratio(
+            DEFAULT_MAX_REFERRAL_COMMISSION.0,
+            DEFAULT_MAX_REFERRAL_COMMISSION.1,
+        ),
+        swaps_frozen: false,
+        provide_frozen: false,
+        withdraw_frozen: false,
+        concentrated: None,
+    };
+    CONFIG.save(deps.storage, &config)?;
+
+    // Create the LP token contract; its address is wired back in `reply`.
+    let sub_msg = SubMsg::reply_on_success(
+        WasmMsg::Instantiate {
+            admin: None,
+            code_id: msg.token_code_id,
+            msg: to_json_binary(&cw20_base::msg::InstantiateMsg {
+                name: "Astroport Metastable LP token".to_string(),
+                symbol: "uLP".to_string(),
+                decimals: 6,
+                initial_balances: vec![],
+                mint: Some(MinterResponse {
+                    minter: env.contract.address.to_string(),
+                    cap: None,
+                }),
+                marketing: None,
+            })?,
+            funds: vec![],
+            label: "Astroport metastable LP token".to_string(),
+        },
+        INSTANTIATE_TOKEN_REPLY_ID,
+    );
+
+    Ok(Response::new().add_submessage(sub_msg))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    ensure!(
+        msg.id == INSTANTIATE_TOKEN_REPLY_ID,
+        ContractError::NonSupported {}
+    );
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure!(
+        config.pair_info.liquidity_token.as_str().is_empty(),
+        ContractError::Unauthorized {}
+    );
+
+    let SubMsgResult::Ok(res) = msg.result else {
+        return Err(ContractError::Std(StdError::generic_err(
+            "LP token instantiation failed",
+        )));
+    };
+    let init_response = cw_utils::parse_instantiate_response_data(
+        res.data
+            .ok_or_else(|| StdError::generic_err("missing instantiate reply data"))?
+            .as_slice(),
+    )
+    .map_err(|e| StdError::generic_err(e.to_string()))?;
+    config.pair_info.liquidity_token = deps.api.addr_validate(&init_response.contract_address)?;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attribute("liquidity_token_addr", config.pair_info.liquidity_token))
+}
+
+/// Resolves the ask asset index for a swap given the offer index.
+///
+/// A two-asset pool has a single unambiguous counterpart. Pools with more than two assets require
+/// the caller to name both assets explicitly, which only the CW20 [`Cw20HookMsg::SwapExactAskAmount`]
+/// hook does (its offer asset is the sent token); an implicit `Swap` cannot pick between several
+/// candidates and is rejected.
+fn default_ask_index(config: &Config, offer_idx: usize) -> Result<usize, ContractError> {
+    match config.pair_info.asset_infos.len() {
+        2 => Ok(if offer_idx == 0 { 1 } else { 0 }),
+        _ => Err(ContractError::NonSupported {}),
+    }
+}
+
+/// Resolves the offer asset index given the ask index, mirroring [`default_ask_index`].
+///
+/// Only unambiguous for two-asset pools; pools with more than two assets must name the offer
+/// asset explicitly rather than relying on an implicit counterpart.
+fn default_offer_index(config: &Config, ask_idx: usize) -> Result<usize, ContractError> {
+    match config.pair_info.asset_infos.len() {
+        2 => Ok(if ask_idx == 0 { 1 } else { 0 }),
+        _ => Err(ContractError::NonSupported {}),
+    }
+}
+
+/// Returns the index of `info` within the pool's asset infos.
+fn asset_index(config: &Config, info: &AssetInfo) -> Result<usize, ContractError> {
+    config
+        .pair_info
+        .asset_infos
+        .iter()
+        .position(|a| a == info)
+        .ok_or(ContractError::AssetMismatch {})
+}
+
+/// Converts a raw balance and its `target_rate` into scaled `Uint256` units for the invariant.
+fn to_scaled(amount: Uint128, rate: Decimal) -> StdResult<Uint256> {
+    scale(Uint256::from(amount), Decimal256::from(rate))
+}
+
+/// Returns the amplification coefficient and the extra `price_scale` factor the invariant is
+/// concentrated around. In concentrated mode both come from [`ConcentratedState`]; otherwise the
+/// pool runs the flat stableswap with `AMP` and a unit price scale.
+fn invariant_params(config: &Config) -> (Uint256, Decimal) {
+    match &config.concentrated {
+        Some(c) => (Uint256::from(c.amp.to_uint_floor()), c.price_scale),
+        None => (Uint256::from(AMP), Decimal::one()),
+    }
+}
+
+/// Builds the per-asset scaling factors for the invariant: each asset's `target_rate`, with the
+/// non-numeraire side additionally multiplied by `price_scale` so the curve concentrates around
+/// the EMA oracle price in concentrated mode.
+fn invariant_rates(config: &Config, n: usize, target_rate: Decimal, price_scale: Decimal) -> Vec<Decimal> {
+    (0..n)
+        .map(|i| {
+            let rate = config.target_rate_for(i, target_rate);
+            if i == 1 {
+                rate * price_scale
+            } else {
+                rate
+            }
+        })
+        .collect()
+}
+
+/// Computes the ask `return_amount`, `spread_amount` and `commission_amount` for a swap, running
+/// the stableswap invariant over balances scaled by their per-asset `target_rate`.
+///
+/// `pools` are the current (pre-swap) pool balances aligned with `config.pair_info.asset_infos`.
+pub fn compute_swap(
+    config: &Config,
+    pools: &[Uint128],
+    offer_idx: usize,
+    ask_idx: usize,
+    offer_amount: Uint128,
+    target_rate: Decimal,
+    commission_rate: Decimal,
+) -> Result<(Uint128, Uint128, Uint128), ContractError> {
+    let (amp, price_scale) = invariant_params(config);
+    let rates = invariant_rates(config, pools.len(), target_rate, price_scale);
+
+    let scaled: Vec<Uint256> = pools
+        .iter()
+        .zip(&rates)
+        .map(|(p, r)| to_scaled(*p, *r))
+        .collect::<StdResult<_>>()?;
+
+    let new_offer = scaled[offer_idx].checked_add(to_scaled(offer_amount, rates[offer_idx])?)?;
+    let new_ask_scaled = compute_y(amp, &scaled, new_offer, offer_idx, ask_idx)?;
+
+    // The untranslated output is the drop in the ask balance, rate-divided back out.
+    let new_ask = Uint128::try_from(unscale(new_ask_scaled, Decimal256::from(rates[ask_idx]))?)
+        .map_err(|e| StdError::generic_err(e.to_string()))?;
+    let gross_return = pools[ask_idx].checked_sub(new_ask)?;
+
+    // Spread is the difference versus an ideal 1:1 (rate-adjusted) price.
+    let ideal = Uint128::try_from(
+        to_scaled(offer_amount, rates[offer_idx])?
+            .checked_div(Decimal256::from(rates[ask_idx]).atomics())
+            .map_err(StdError::divide_by_zero)?
+            * Uint256::from(10u128).pow(Decimal256::DECIMAL_PLACES),
+    )
+    .unwrap_or(gross_return);
+    let spread_amount = ideal.saturating_sub(gross_return);
+
+    let commission_amount = gross_return * commission_rate;
+    let return_amount = gross_return.checked_sub(commission_amount)?;
+
+    Ok((return_amount, spread_amount, commission_amount))
+}
+
+/// Solves the reverse invariant: the offer amount required to receive exactly `ask_amount` out.
+pub fn compute_offer(
+    config: &Config,
+    pools: &[Uint128],
+    offer_idx: usize,
+    ask_idx: usize,
+    ask_amount: Uint128,
+    target_rate: Decimal,
+    commission_rate: Decimal,
+) -> Result<(Uint128, Uint128, Uint128), ContractError> {
+    let (amp, price_scale) = invariant_params(config);
+    let rates = invariant_rates(config, pools.len(), target_rate, price_scale);
+
+    // Gross the ask up by the commission so the net amount received is `ask_amount`.
+    let one_minus_commission = Decimal::one() - commission_rate;
+    let gross_ask = ask_amount * Decimal::one() / one_minus_commission;
+    let commission_amount = gross_ask.checked_sub(ask_amount)?;
+
+    let scaled: Vec<Uint256> = pools
+        .iter()
+        .zip(&rates)
+        .map(|(p, r)| to_scaled(*p, *r))
+        .collect::<StdResult<_>>()?;
+
+    let new_ask = scaled[ask_idx].checked_sub(to_scaled(gross_ask, rates[ask_idx])?)?;
+    let new_offer_scaled = compute_y(amp, &scaled, new_ask, ask_idx, offer_idx)?;
+    let new_offer = Uint128::try_from(unscale(new_offer_scaled, Decimal256::from(rates[offer_idx]))?)
+        .map_err(|e| StdError::generic_err(e.to_string()))?;
+    let offer_amount = new_offer.checked_sub(pools[offer_idx])?;
+
+    let ideal = gross_ask * rates[ask_idx] / rates[offer_idx];
+    let spread_amount = offer_amount.saturating_sub(ideal);
+
+    Ok((offer_amount, spread_amount, commission_amount))
+}
+
+/// Validates `referral_commission` against the config max and returns the referral cut taken from
+/// `offer_amount`, together with the net offer amount that continues into the swap.
+pub fn take_referral(
+    config: &Config,
+    offer_amount: Uint128,
+    referral_commission: Option<Decimal>,
+) -> Result<(Uint128, Uint128), ContractError> {
+    let commission = referral_commission.unwrap_or_default();
+    if commission.is_zero() {
+        return Ok((Uint128::zero(), offer_amount));
+    }
+    ensure!(
+        commission <= config.max_referral_commission,
+        ContractError::ReferralCommissionTooHigh {
+            commission: commission.to_string(),
+            max: config.max_referral_commission.to_string(),
+        }
+    );
+    let referral_amount = offer_amount * commission;
+    Ok((referral_amount, offer_amount.checked_sub(referral_amount)?))
+}
+
+/// Builds the transfer that pays the referral cut (in the offer asset) to `referral_addr`.
+pub fn referral_transfer_msg(
+    offer_info: &AssetInfo,
+    referral_addr: &Addr,
+    amount: Uint128,
+) -> StdResult<Option<CosmosMsg>> {
+    if amount.is_zero() {
+        return Ok(None);
+    }
+    let asset = Asset {
+        info: offer_info.clone(),
+        amount,
+    };
+    Ok(Some(asset.into_msg(referral_addr)?))
+}
+
+/// Asserts that the realized swap price stays within the caller's slippage tolerance.
+pub fn assert_max_spread(
+    belief_price: Option<Decimal>,
+    max_spread: Option<Decimal>,
+    offer_amount: Uint128,
+    return_amount: Uint128,
+    spread_amount: Uint128,
+) -> Result<(), ContractError> {
+    let max_spread = max_spread.unwrap_or(Decimal::percent(50));
+    if let Some(belief_price) = belief_price {
+        let expected_return = offer_amount
+            * belief_price.inv().ok_or(ContractError::AllowedSpreadAssertion {})?;
+        let spread = expected_return.saturating_sub(return_amount);
+        if return_amount < expected_return
+            && Decimal::from_ratio(spread, expected_return) > max_spread
+        {
+            return Err(ContractError::MaxSpreadAssertion {});
+        }
+    } else if !return_amount.is_zero()
+        && Decimal::from_ratio(spread_amount, return_amount + spread_amount) > max_spread
+    {
+        return Err(ContractError::MaxSpreadAssertion {});
+    }
+    Ok(())
+}
+
+/// Ensures the deposited asset ratios stay within `slippage_tolerance` of the current pool ratios,
+/// so a liquidity provider cannot be sandwiched into a skewed deposit. Assets the provider omitted
+/// (zero deposit) are skipped, preserving subset-provide; the check runs over every pair of assets
+/// the provider actually funded. The first deposit into an empty pool sets the price and is exempt.
+pub fn assert_slippage_tolerance(
+    slippage_tolerance: Option<Decimal>,
+    deposits: &[Uint128],
+    pools: &[Uint128],
+) -> Result<(), ContractError> {
+    let slippage_tolerance = slippage_tolerance
+        .unwrap_or_else(|| Decimal::from_str(DEFAULT_SLIPPAGE).unwrap());
+    if slippage_tolerance > Decimal::from_str(MAX_ALLOWED_SLIPPAGE).unwrap() {
+        return Err(ContractError::AllowedSpreadAssertion {});
+    }
+    let one_minus_slippage = Decimal256::one() - Decimal256::from(slippage_tolerance);
+
+    // Indices of the assets the provider actually funded.
+    let funded: Vec<usize> = deposits
+        .iter()
+        .enumerate()
+        .filter(|(_, d)| !d.is_zero())
+        .map(|(i, _)| i)
+        .collect();
+    // A first-time deposit into an empty pool sets the initial price; nothing to compare against.
+    if funded.iter().any(|&i| pools[i].is_zero()) {
+        return Ok(());
+    }
+
+    for (a, &i) in funded.iter().enumerate() {
+        for &j in &funded[a + 1..] {
+            if Decimal256::from_ratio(deposits[i], deposits[j]) * one_minus_slippage
+                > Decimal256::from_ratio(pools[i], pools[j])
+                || Decimal256::from_ratio(deposits[j], deposits[i]) * one_minus_slippage
+                    > Decimal256::from_ratio(pools[j], pools[i])
+            {
+                return Err(ContractError::MaxSlippageAssertion {});
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The flat stable-pair commission rate charged outside passive-concentrated mode.
+///
+/// In passive-concentrated mode [`effective_fee`] charges the dynamic fee instead, which
+/// interpolates between `mid_fee` (balanced pool) and `out_fee` (imbalanced) by the factor
+/// `fee_gamma / (fee_gamma + (1 - balance_ratio))`.
+fn commission_rate() -> Decimal {
+    // 0.3% default, matching the stable pair.
+    Decimal::from_ratio(3u128, 1000u128)
+}
+
+/// Returns the effective fee rate for the given pool balances, dynamic in concentrated mode.
+fn effective_fee(config: &Config, pools: &[Uint128]) -> Decimal {
+    match &config.concentrated {
+        Some(c) => dynamic_fee(c, balance_ratio(pools)),
+        None => commission_rate(),
+    }
+}
+
+/// The balance ratio `min(pools) / max(pools)` in `[0, 1]`; `1` is a perfectly balanced pool.
+fn balance_ratio(pools: &[Uint128]) -> Decimal {
+    let max = pools.iter().max().copied().unwrap_or_default();
+    let min = pools.iter().min().copied().unwrap_or_default();
+    if max.is_zero() {
+        Decimal::one()
+    } else {
+        Decimal::from_ratio(min, max)
+    }
+}
+
+/// Interpolates the dynamic fee between `mid_fee` and `out_fee`.
+fn dynamic_fee(c: &crate::state::ConcentratedState, balance_ratio: Decimal) -> Decimal {
+    let imbalance = Decimal::one() - balance_ratio;
+    let denom = c.fee_gamma + imbalance;
+    if denom.is_zero() {
+        return c.mid_fee;
+    }
+    let k = c.fee_gamma / denom;
+    // fee = k·mid_fee + (1 - k)·out_fee
+    c.mid_fee * k + c.out_fee * (Decimal::one() - k)
+}
+
+/// Computes an EMA of the current `price_scale` and the latest executed trade price, weighted by
+/// `ma_half_time`, adds `profit_gain` to `xcp_profit`, and sets `price_scale` to that EMA only when
+/// `profit_gain` reaches `repeg_profit_threshold`. No separate oracle price is stored: below the
+/// threshold `price_scale` is left unchanged and the EMA step is not kept.
+fn update_price_oracle(config: &mut Config, env: &Env, trade_price: Decimal, profit_gain: Decimal) {
+    let Some(c) = config.concentrated.as_mut() else {
+        return;
+    };
+    let dt = env.block.time.seconds().saturating_sub(c.last_update);
+    // Discrete EMA weight approximated as dt / (dt + ma_half_time).
+    let alpha = if c.ma_half_time == 0 {
+        Decimal::one()
+    } else {
+        Decimal::from_ratio(dt, dt + c.ma_half_time)
+    };
+    let oracle = c.price_scale * (Decimal::one() - alpha) + trade_price * alpha;
+
+    c.xcp_profit += profit_gain;
+    if profit_gain >= c.repeg_profit_threshold {
+        c.price_scale = oracle;
+    }
+    c.last_update = env.block.time.seconds();
+}
+
+/// Returns the current pool balances aligned with `config.pair_info.asset_infos`.
+fn query_pools(deps: Deps, env: &Env, config: &Config) -> StdResult<Vec<Uint128>> {
+    config
+        .pair_info
+        .query_pools(&deps.querier, &env.contract.address)
+        .map(|pools| pools.into_iter().map(|p| p.amount).collect())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query_config(deps: Deps, env: Env) -> StdResult<ConfigResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let target_rate = config
+        .resolve_target_rate(deps, env.block.time.seconds())
+        .unwrap_or(config.target_rate);
+    Ok(ConfigResponse {
+        hub_address: config.hub_address.clone(),
+        stluna_address: config.stluna_address.clone(),
+        bluna_address: config.bluna_address.clone(),
+        block_time_last: config.block_time_last,
+        target_rate,
+        target_rate_last: config.target_rate_last,
+        max_referral_commission: config.max_referral_commission,
+        swaps_frozen: config.swaps_frozen,
+        provide_frozen: config.provide_frozen,
+        withdraw_frozen: config.withdraw_frozen,
+        price_scale: config
+            .concentrated
+            .as_ref()
+            .map(|c| c.price_scale)
+            .unwrap_or_else(Decimal::one),
+        xcp_profit: config
+            .concentrated
+            .as_ref()
+            .map(|c| c.xcp_profit)
+            .unwrap_or_default(),
+    })
+}
+
+/// Simulates a swap, reflecting the rate-adjusted invariant.
+pub fn query_simulation(
+    deps: Deps,
+    env: Env,
+    offer_asset: Asset,
+    referral_commission: Option<Decimal>,
+) -> Result<SimulationResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let pools = query_pools(deps, &env, &config)?;
+    let offer_idx = asset_index(&config, &offer_asset.info)?;
+    let ask_idx = default_ask_index(&config, offer_idx)?;
+    let target_rate = config.resolve_target_rate(deps, env.block.time.seconds())?;
+
+    let (referral_amount, net_offer) =
+        take_referral(&config, offer_asset.amount, referral_commission)?;
+
+    let (return_amount, spread_amount, commission_amount) = compute_swap(
+        &config,
+        &pools,
+        offer_idx,
+        ask_idx,
+        net_offer,
+        target_rate,
+        effective_fee(&config, &pools),
+    )?;
+
+    Ok(SimulationResponse {
+        return_amount,
+        spread_amount,
+        commission_amount,
+        referral_amount,
+    })
+}
+
+/// Reverse-simulates a swap, reflecting the rate-adjusted invariant.
+pub fn query_reverse_simulation(
+    deps: Deps,
+    env: Env,
+    ask_asset: Asset,
+    referral_commission: Option<Decimal>,
+) -> Result<ReverseSimulationResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let pools = query_pools(deps, &env, &config)?;
+    let ask_idx = asset_index(&config, &ask_asset.info)?;
+    let offer_idx = default_offer_index(&config, ask_idx)?;
+    let target_rate = config.resolve_target_rate(deps, env.block.time.seconds())?;
+
+    let (swap_offer, spread_amount, commission_amount) = compute_offer(
+        &config,
+        &pools,
+        offer_idx,
+        ask_idx,
+        ask_asset.amount,
+        target_rate,
+        effective_fee(&config, &pools),
+    )?;
+
+    // The referral cut is taken from the offer, so the gross offer must be grossed up by it.
+    let commission = referral_commission.unwrap_or_default();
+    ensure!(
+        commission <= config.max_referral_commission,
+        ContractError::ReferralCommissionTooHigh {
+            commission: commission.to_string(),
+            max: config.max_referral_commission.to_string(),
+        }
+    );
+    let offer_amount = swap_offer * Decimal::one() / (Decimal::one() - commission);
+    let referral_amount = offer_amount.checked_sub(swap_offer)?;
+
+    Ok(ReverseSimulationResponse {
+        offer_amount,
+        spread_amount,
+        commission_amount,
+        referral_amount,
+    })
+}
+
+/// Refreshes and persists the cached hub exchange rate when the staleness window has elapsed.
+pub fn refresh_target_rate(
+    deps: DepsMut,
+    env: &Env,
+    config: &mut Config,
+) -> Result<Decimal, ContractError> {
+    let block_time = env.block.time.seconds();
+    if block_time >= config.target_rate_last + config.ma_half_time || config.target_rate.is_zero() {
+        let rate = config.query_hub_rate(deps.as_ref())?;
+        ensure!(!rate.is_zero(), ContractError::InvalidExchangeRate {});
+        config.target_rate = rate;
+        config.target_rate_last = block_time;
+        CONFIG.save(deps.storage, config)?;
+    }
+    Ok(config.target_rate)
+}
+
+/// Ensures `addr` is the pair owner.
+pub fn assert_owner(config: &Config, sender: &Addr) -> Result<(), ContractError> {
+    ensure!(sender == config.owner, ContractError::Unauthorized {});
+    Ok(())
+}
+
+/// Returns an error when swaps are frozen for this pair.
+pub fn assert_swaps_enabled(config: &Config) -> Result<(), ContractError> {
+    ensure!(!config.swaps_frozen, ContractError::SwapsFrozen {});
+    Ok(())
+}
+
+/// Returns an error when liquidity provision is frozen for this pair.
+pub fn assert_provide_enabled(config: &Config) -> Result<(), ContractError> {
+    ensure!(!config.provide_frozen, ContractError::ProvideFrozen {});
+    Ok(())
+}
+
+/// Returns an error when liquidity withdrawals are frozen for this pair.
+pub fn assert_withdraw_enabled(config: &Config) -> Result<(), ContractError> {
+    ensure!(!config.withdraw_frozen, ContractError::WithdrawFrozen {});
+    Ok(())
+}
+
+/// Owner-gated: disables or re-enables the whole pair at once by setting every freeze flag.
+pub fn execute_update_pair_status(
+    deps: DepsMut,
+    info: MessageInfo,
+    is_disabled: bool,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+    config.swaps_frozen = is_disabled;
+    config.provide_frozen = is_disabled;
+    config.withdraw_frozen = is_disabled;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_pair_status")
+        .add_attribute("is_disabled", is_disabled.to_string()))
+}
+
+/// Owner-gated: independently freezes or unfreezes swaps, liquidity provision and withdrawals.
+pub fn execute_update_pair_freeze(
+    deps: DepsMut,
+    info: MessageInfo,
+    freeze_swaps: Option<bool>,
+    freeze_provide: Option<bool>,
+    freeze_withdraw: Option<bool>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+    if let Some(v) = freeze_swaps {
+        config.swaps_frozen = v;
+    }
+    if let Some(v) = freeze_provide {
+        config.provide_frozen = v;
+    }
+    if let Some(v) = freeze_withdraw {
+        config.withdraw_frozen = v;
+    }
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_pair_freeze")
+        .add_attribute("swaps_frozen", config.swaps_frozen.to_string())
+        .add_attribute("provide_frozen", config.provide_frozen.to_string())
+        .add_attribute("withdraw_frozen", config.withdraw_frozen.to_string()))
+}
+
+/// Updates the opaque pair parameters. When the binary decodes to [`ConcentratedPoolParams`], the
+/// pool enters passive-concentrated mode with an EMA oracle seeded at `price_scale = 1`.
+pub fn update_params(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    params: Binary,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+    if let Ok(p) = cosmwasm_std::from_json::<ConcentratedPoolParams>(&params) {
+        let price_scale = config
+            .concentrated
+            .as_ref()
+            .map(|c| c.price_scale)
+            .unwrap_or_else(Decimal::one);
+        let xcp_profit = config
+            .concentrated
+            .as_ref()
+            .map(|c| c.xcp_profit)
+            .unwrap_or_default();
+        config.concentrated = Some(ConcentratedState {
+            amp: p.amp,
+            gamma: p.gamma,
+            mid_fee: p.mid_fee,
+            out_fee: p.out_fee,
+            fee_gamma: p.fee_gamma,
+            repeg_profit_threshold: p.repeg_profit_threshold,
+            ma_half_time: p.ma_half_time,
+            price_scale,
+            xcp_profit,
+            last_update: env.block.time.seconds(),
+        });
+        CONFIG.save(deps.storage, &config)?;
+    }
+    Ok(Response::new().add_attribute("action", "update_config"))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::Receive(cw20_msg) => receive_cw20(deps, env, info, cw20_msg),
+        ExecuteMsg::ProvideLiquidity {
+            assets,
+            slippage_tolerance,
+            receiver,
+        } => execute_provide_liquidity(deps, env, info, assets, slippage_tolerance, receiver),
+        ExecuteMsg::Swap {
+            offer_asset,
+            belief_price,
+            max_spread,
+            to,
+            referral_address,
+            referral_commission,
+        } => {
+            offer_asset.assert_sent_native_token_balance(&info)?;
+            let to_addr = to.map(|t| deps.api.addr_validate(&t)).transpose()?;
+            let referral_addr = referral_address
+                .map(|r| deps.api.addr_validate(&r))
+                .transpose()?;
+            execute_swap(
+                deps,
+                env,
+                info.sender,
+                offer_asset,
+                belief_price,
+                max_spread,
+                to_addr,
+                referral_addr,
+                referral_commission,
+            )
+        }
+        ExecuteMsg::SwapExactAskAmount {
+            ask_asset,
+            max_offer_amount,
+            to,
+        } => {
+            // Native exact-ask: the offer is the non-ask asset, provided as attached funds.
+            let to_addr = to.map(|t| deps.api.addr_validate(&t)).transpose()?;
+            execute_swap_exact_ask(deps, env, info, ask_asset, max_offer_amount, to_addr)
+        }
+        ExecuteMsg::UpdateConfig { params } => update_params(deps, env, info, params),
+        ExecuteMsg::UpdatePairStatus { is_disabled } => {
+            execute_update_pair_status(deps, info, is_disabled)
+        }
+        ExecuteMsg::UpdatePairFreeze {
+            freeze_swaps,
+            freeze_provide,
+            freeze_withdraw,
+        } => execute_update_pair_freeze(deps, info, freeze_swaps, freeze_provide, freeze_withdraw),
+    }
+}
+
+/// Routes the CW20 hook variants to their handlers.
+pub fn receive_cw20(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    cw20_msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let sender = deps.api.addr_validate(&cw20_msg.sender)?;
+    match cosmwasm_std::from_json(&cw20_msg.msg)? {
+        Cw20HookMsg::Swap {
+            belief_price,
+            max_spread,
+            to,
+            referral_address,
+            referral_commission,
+        } => {
+            let offer_asset = Asset {
+                info: AssetInfo::Token {
+                    contract_addr: info.sender.clone(),
+                },
+                amount: cw20_msg.amount,
+            };
+            let to_addr = to.map(|t| deps.api.addr_validate(&t)).transpose()?;
+            let referral_addr = referral_address
+                .map(|r| deps.api.addr_validate(&r))
+                .transpose()?;
+            execute_swap(
+                deps,
+                env,
+                sender,
+                offer_asset,
+                belief_price,
+                max_spread,
+                to_addr,
+                referral_addr,
+                referral_commission,
+            )
+        }
+        Cw20HookMsg::SwapExactAskAmount {
+            ask_asset,
+            max_offer_amount,
+            to,
+        } => {
+            let offer_asset = Asset {
+                info: AssetInfo::Token {
+                    contract_addr: info.sender.clone(),
+                },
+                amount: cw20_msg.amount,
+            };
+            let to_addr = to.map(|t| deps.api.addr_validate(&t)).transpose()?;
+            execute_swap_exact_ask_with_offer(
+                deps,
+                env,
+                sender,
+                offer_asset,
+                ask_asset,
+                max_offer_amount,
+                to_addr,
+            )
+        }
+        Cw20HookMsg::WithdrawLiquidity {} => {
+            execute_withdraw_liquidity(deps, env, sender, cw20_msg.amount)
+        }
+    }
+}
+
+/// Performs a swap for a variable output amount, honouring the `swaps_frozen` circuit-breaker and
+/// paying out any referral cut taken from the offer.
+#[allow(clippy::too_many_arguments)]
+pub fn execute_swap(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    offer_asset: Asset,
+    belief_price: Option<Decimal>,
+    max_spread: Option<Decimal>,
+    to: Option<Addr>,
+    referral_addr: Option<Addr>,
+    referral_commission: Option<Decimal>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_swaps_enabled(&config)?;
+
+    let target_rate = refresh_target_rate(deps.branch(), &env, &mut config)?;
+    let pools = query_pools(deps.as_ref(), &env, &config)?;
+    let offer_idx = asset_index(&config, &offer_asset.info)?;
+    let ask_idx = default_ask_index(&config, offer_idx)?;
+    let ask_info = config.pair_info.asset_infos[ask_idx].clone();
+
+    // A referral cut may only be deducted when there is a validated address to pay it to,
+    // otherwise the deducted amount would be silently absorbed by the pool.
+    if referral_commission.map(|c| !c.is_zero()).unwrap_or(false) && referral_addr.is_none() {
+        return Err(ContractError::ReferralAddressRequired {});
+    }
+    let (referral_amount, net_offer) = if referral_addr.is_some() {
+        take_referral(&config, offer_asset.amount, referral_commission)?
+    } else {
+        (Uint128::zero(), offer_asset.amount)
+    };
+
+    let (return_amount, spread_amount, commission_amount) = compute_swap(
+        &config,
+        &pools,
+        offer_idx,
+        ask_idx,
+        net_offer,
+        target_rate,
+        effective_fee(&config, &pools),
+    )?;
+
+    assert_max_spread(belief_price, max_spread, net_offer, return_amount, spread_amount)?;
+
+    // Concentrated mode: feed the executed trade price into the EMA oracle and repeg if profitable.
+    if config.concentrated.is_some() && !net_offer.is_zero() {
+        let trade_price = Decimal::from_ratio(return_amount, net_offer);
+        let profit_gain = Decimal::from_ratio(commission_amount, net_offer);
+        update_price_oracle(&mut config, &env, trade_price, profit_gain);
+        CONFIG.save(deps.storage, &config)?;
+    }
+
+    let receiver = to.unwrap_or_else(|| sender.clone());
+    let mut messages: Vec<CosmosMsg> = vec![Asset {
+        info: ask_info,
+        amount: return_amount,
+    }
+    .into_msg(&receiver)?];
+
+    let mut response = Response::new();
+    if let Some(referrer) = referral_addr {
+        if let Some(msg) = referral_transfer_msg(&offer_asset.info, &referrer, referral_amount)? {
+            messages.push(msg);
+            response = response
+                .add_attribute("referral_address", referrer.to_string())
+                .add_attribute("referral_amount", referral_amount.to_string());
+        }
+    }
+
+    Ok(response.add_messages(messages).add_attributes(vec![
+        ("action", "swap"),
+        ("sender", sender.as_str()),
+        ("receiver", receiver.as_str()),
+        ("return_amount", &return_amount.to_string()),
+        ("spread_amount", &spread_amount.to_string()),
+        ("commission_amount", &commission_amount.to_string()),
+    ]))
+}
+
+/// Provides liquidity, honouring the `provide_frozen` circuit-breaker.
+pub fn execute_provide_liquidity(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    assets: Vec<Asset>,
+    slippage_tolerance: Option<Decimal>,
+    receiver: Option<String>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_provide_enabled(&config)?;
+    provide_liquidity_inner(deps, env, info, config, assets, slippage_tolerance, receiver)
+}
+
+/// Withdraws liquidity, honouring the `withdraw_frozen` circuit-breaker. Withdrawals stay open
+/// even while swaps are halted.
+pub fn execute_withdraw_liquidity(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_withdraw_enabled(&config)?;
+    withdraw_liquidity_inner(deps, env, config, sender, amount)
+}
+
+/// Mints LP tokens proportional to the growth of the invariant `D` when `assets` are deposited.
+/// LPs may provide any subset of the pool assets; omitted assets simply contribute a zero balance.
+fn provide_liquidity_inner(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    mut config: Config,
+    assets: Vec<Asset>,
+    slippage_tolerance: Option<Decimal>,
+    receiver: Option<String>,
+) -> Result<Response, ContractError> {
+    let n = config.pair_info.asset_infos.len();
+    debug_assert!(n <= MAX_ASSETS);
+    // LPs may provide any non-empty subset of the pool's assets.
+    ensure!(
+        !assets.is_empty() && assets.len() <= n,
+        ContractError::InvalidNumberOfAssets {
+            max: n,
+            provided: assets.len(),
+        }
+    );
+
+    for asset in &assets {
+        asset.assert_sent_native_token_balance(&info)?;
+    }
+
+    let target_rate = refresh_target_rate(deps.branch(), &env, &mut config)?;
+    let (amp, price_scale) = invariant_params(&config);
+    let rates = invariant_rates(&config, n, target_rate, price_scale);
+
+    let deposits: Vec<Uint128> = config
+        .pair_info
+        .asset_infos
+        .iter()
+        .map(|info| {
+            assets
+                .iter()
+                .find(|a| &a.info == info)
+                .map(|a| a.amount)
+                .unwrap_or_default()
+        })
+        .collect();
+
+    // Collect CW20 deposits via `TransferFrom`. Native funds already arrived with the message, so
+    // the current balance already reflects them; CW20 tokens are only pulled here, meaning the
+    // current balance is still the pre-deposit ("before") balance for those assets. Computing the
+    // invariant off the declared deposits (rather than a future balance) closes the phantom-deposit
+    // hole where a caller could mint LP against collateral that never arrives.
+    let balances = query_pools(deps.as_ref(), &env, &config)?;
+    let mut transfer_msgs: Vec<CosmosMsg> = vec![];
+    let mut pools_before = Vec::with_capacity(n);
+    for (i, asset_info) in config.pair_info.asset_infos.iter().enumerate() {
+        match asset_info {
+            AssetInfo::NativeToken { .. } => {
+                // The sent coins are in `balances`; strip them to recover the prior balance.
+                pools_before.push(balances[i].checked_sub(deposits[i])?);
+            }
+            AssetInfo::Token { contract_addr } => {
+                pools_before.push(balances[i]);
+                if !deposits[i].is_zero() {
+                    transfer_msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                        contract_addr: contract_addr.to_string(),
+                        msg: to_json_binary(&cw20::Cw20ExecuteMsg::TransferFrom {
+                            owner: info.sender.to_string(),
+                            recipient: env.contract.address.to_string(),
+                            amount: deposits[i],
+                        })?,
+                        funds: vec![],
+                    }));
+                }
+            }
+        }
+    }
+
+    // Guard the provider against the pool ratio moving under them between simulation and execution.
+    assert_slippage_tolerance(slippage_tolerance, &deposits, &pools_before)?;
+
+    let pools_after: Vec<Uint128> = pools_before
+        .iter()
+        .zip(&deposits)
+        .map(|(p, d)| p.checked_add(*d))
+        .collect::<StdResult<_>>()?;
+
+    let scale_for = |balances: &[Uint128]| -> StdResult<Vec<Uint256>> {
+        balances
+            .iter()
+            .zip(&rates)
+            .map(|(b, r)| scale(Uint256::from(*b), Decimal256::from(*r)))
+            .collect()
+    };
+
+    let d_before = compute_d(amp, &scale_for(&pools_before)?)?;
+    let d_after = compute_d(amp, &scale_for(&pools_after)?)?;
+
+    let total_share = astroport::querier::query_supply(
+        &deps.querier,
+        config.pair_info.liquidity_token.clone(),
+    )?;
+
+    let share = if total_share.is_zero() {
+        Uint128::try_from(d_after).map_err(|e| StdError::generic_err(e.to_string()))?
+    } else {
+        let minted = d_after
+            .checked_sub(d_before)?
+            .checked_mul(Uint256::from(total_share))?
+            .checked_div(d_before)
+            .map_err(StdError::divide_by_zero)?;
+        Uint128::try_from(minted).map_err(|e| StdError::generic_err(e.to_string()))?
+    };
+    ensure!(!share.is_zero(), ContractError::InvalidZeroAmount {});
+
+    // LP tokens are minted to `receiver` when supplied, otherwise to the depositor.
+    let receiver = receiver
+        .map(|r| deps.api.addr_validate(&r))
+        .transpose()?
+        .unwrap_or_else(|| info.sender.clone());
+    let mint_msg = astroport::querier::mint_liquidity_token_message(
+        &config.pair_info.liquidity_token,
+        &receiver,
+        share,
+    )?;
+
+    // CW20 deposits are pulled first, then LP is minted against the collected collateral.
+    Ok(Response::new()
+        .add_messages(transfer_msgs)
+        .add_messages(mint_msg)
+        .add_attributes(vec![
+            ("action", "provide_liquidity".to_string()),
+            ("share", share.to_string()),
+        ]))
+}
+
+/// Burns `amount` LP tokens and returns each pool asset pro-rata to the withdrawer.
+fn withdraw_liquidity_inner(
+    deps: DepsMut,
+    env: Env,
+    config: Config,
+    sender: Addr,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let pools = query_pools(deps.as_ref(), &env, &config)?;
+    let total_share = astroport::querier::query_supply(
+        &deps.querier,
+        config.pair_info.liquidity_token.clone(),
+    )?;
+    ensure!(!total_share.is_zero(), ContractError::InvalidZeroAmount {});
+
+    let refund: Vec<Asset> = config
+        .pair_info
+        .asset_infos
+        .iter()
+        .zip(&pools)
+        .map(|(info, pool)| Asset {
+            info: info.clone(),
+            amount: pool.multiply_ratio(amount, total_share),
+        })
+        .collect();
+
+    let mut messages: Vec<CosmosMsg> = refund
+        .iter()
+        .map(|asset| asset.into_msg(&sender))
+        .collect::<StdResult<_>>()?;
+    messages.push(astroport::querier::burn_liquidity_token_message(
+        &config.pair_info.liquidity_token,
+        amount,
+    )?);
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "withdraw_liquidity")
+        .add_attribute("withdrawn_share", amount.to_string()))
+}
+
+/// Exact-output swap funded by attached native coins: solves the reverse invariant on-chain,
+/// consumes at most `max_offer_amount` of the offer asset and refunds any unused offer.
+pub fn execute_swap_exact_ask(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    ask_asset: Asset,
+    max_offer_amount: Uint128,
+    to: Option<Addr>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    // Reject a frozen pair before touching the caller's attached funds.
+    assert_swaps_enabled(&config)?;
+    let ask_idx = asset_index(&config, &ask_asset.info)?;
+    let offer_idx = default_offer_index(&config, ask_idx)?;
+    let offer_info = config.pair_info.asset_infos[offer_idx].clone();
+    // A CW20 offer cannot arrive as attached funds; it goes through the CW20 hook instead.
+    ensure!(
+        matches!(offer_info, AssetInfo::NativeToken { .. }),
+        ContractError::NonSupported {}
+    );
+
+    // The caller must attach at least `max_offer_amount` of the offer asset; refunds settle later.
+    let offer_asset = Asset {
+        info: offer_info,
+        amount: max_offer_amount,
+    };
+    offer_asset.assert_sent_native_token_balance(&info)?;
+
+    execute_swap_exact_ask_with_offer(
+        deps,
+        env,
+        info.sender,
+        offer_asset,
+        ask_asset,
+        max_offer_amount,
+        to,
+    )
+}
+
+/// Exact-output swap core shared by the native and CW20 entry points.
+///
+/// Solves the reverse invariant for the offer needed to produce exactly `ask_asset.amount`, fails
+/// atomically when that offer exceeds `max_offer_amount`, performs the swap and refunds the unused
+/// portion of `offer_asset` back to the sender.
+#[allow(clippy::too_many_arguments)]
+pub fn execute_swap_exact_ask_with_offer(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    offer_asset: Asset,
+    ask_asset: Asset,
+    max_offer_amount: Uint128,
+    to: Option<Addr>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_swaps_enabled(&config)?;
+
+    ensure!(!ask_asset.amount.is_zero(), ContractError::InvalidZeroAmount {});
+
+    let target_rate = refresh_target_rate(deps.branch(), &env, &mut config)?;
+    let pools = query_pools(deps.as_ref(), &env, &config)?;
+    let ask_idx = asset_index(&config, &ask_asset.info)?;
+    let offer_idx = asset_index(&config, &offer_asset.info)?;
+    ensure!(offer_idx != ask_idx, ContractError::AssetMismatch {});
+
+    let (required_offer, spread_amount, commission_amount) = compute_offer(
+        &config,
+        &pools,
+        offer_idx,
+        ask_idx,
+        ask_asset.amount,
+        target_rate,
+        effective_fee(&config, &pools),
+    )?;
+
+    ensure!(
+        required_offer <= max_offer_amount,
+        ContractError::MaxOfferAmountAssertion {
+            required: required_offer.to_string(),
+            max: max_offer_amount.to_string(),
+        }
+    );
+
+    // Concentrated mode: exact-output swaps feed the EMA oracle and repeg just like variable-output
+    // swaps, otherwise router-driven flow would silently skip the price-scale update.
+    if config.concentrated.is_some() && !required_offer.is_zero() {
+        let trade_price = Decimal::from_ratio(ask_asset.amount, required_offer);
+        let profit_gain = Decimal::from_ratio(commission_amount, required_offer);
+        update_price_oracle(&mut config, &env, trade_price, profit_gain);
+        CONFIG.save(deps.storage, &config)?;
+    }
+
+    let receiver = to.unwrap_or_else(|| sender.clone());
+    let mut messages: Vec<CosmosMsg> = vec![Asset {
+        info: ask_asset.info.clone(),
+        amount: ask_asset.amount,
+    }
+    .into_msg(&receiver)?];
+
+    // Refund the unused offer back to the sender.
+    let refund = offer_asset.amount.checked_sub(required_offer)?;
+    if !refund.is_zero() {
+        messages.push(
+            Asset {
+                info: offer_asset.info.clone(),
+                amount: refund,
+            }
+            .into_msg(&sender)?,
+        );
+    }
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        ("action", "swap_exact_ask_amount".to_string()),
+        ("sender", sender.to_string()),
+        ("receiver", receiver.to_string()),
+        ("offer_amount", required_offer.to_string()),
+        ("ask_amount", ask_asset.amount.to_string()),
+        ("refund_amount", refund.to_string()),
+        ("spread_amount", spread_amount.to_string()),
+        ("commission_amount", commission_amount.to_string()),
+    ]))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
+    match msg {
+        QueryMsg::Pair {} => Ok(to_json_binary(&CONFIG.load(deps.storage)?.pair_info)?),
+        QueryMsg::Pool {} => Ok(to_json_binary(&query_pool(deps, env)?)?),
+        QueryMsg::Config {} => Ok(to_json_binary(&query_config(deps, env)?)?),
+        QueryMsg::Share { amount } => Ok(to_json_binary(&query_share(deps, env, amount)?)?),
+        QueryMsg::Simulation {
+            offer_asset,
+            referral_commission,
+        } => Ok(to_json_binary(&query_simulation(
+            deps,
+            env,
+            offer_asset,
+            referral_commission,
+        )?)?),
+        QueryMsg::ReverseSimulation {
+            ask_asset,
+            referral_commission,
+        } => Ok(to_json_binary(&query_reverse_simulation(
+            deps,
+            env,
+            ask_asset,
+            referral_commission,
+        )?)?),
+        QueryMsg::CumulativePrices {} => {
+            Ok(to_json_binary(&query_cumulative_prices(deps, env)?)?)
+        }
+    }
+}
+
+/// Returns the pool balances and total LP share.
+pub fn query_pool(deps: Deps, env: Env) -> Result<PoolResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let assets: Vec<Asset> = config
+        .pair_info
+        .asset_infos
+        .iter()
+        .zip(query_pools(deps, &env, &config)?)
+        .map(|(info, amount)| Asset {
+            info: info.clone(),
+            amount,
+        })
+        .collect();
+    let total_share =
+        astroport::querier::query_supply(&deps.querier, config.pair_info.liquidity_token)?;
+    Ok(PoolResponse {
+        assets,
+        total_share,
+    })
+}
+
+/// Returns the pro-rata assets backing `amount` LP tokens.
+pub fn query_share(deps: Deps, env: Env, amount: Uint128) -> Result<Vec<Asset>, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let pools = query_pools(deps, &env, &config)?;
+    let total_share =
+        astroport::querier::query_supply(&deps.querier, config.pair_info.liquidity_token.clone())?;
+    if total_share.is_zero() {
+        return Ok(vec![]);
+    }
+    Ok(config
+        .pair_info
+        .asset_infos
+        .iter()
+        .zip(&pools)
+        .map(|(info, pool)| Asset {
+            info: info.clone(),
+            amount: pool.multiply_ratio(amount, total_share),
+        })
+        .collect())
+}
+
+/// Returns, for every ordered pair combination of the pool's assets, the return of a one-unit swap
+/// at the current balances with no commission.
+///
+/// A pool of `n` assets records `n·(n-1)` tuples, one per directed asset pair, so any asset is
+/// priced against any other — not just the two sides of a pair. The values are spot samples taken
+/// at query time; they are not accumulated over time.
+pub fn query_cumulative_prices(
+    deps: Deps,
+    env: Env,
+) -> Result<CumulativePricesResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let pools = query_pools(deps, &env, &config)?;
+    let total_share =
+        astroport::querier::query_supply(&deps.querier, config.pair_info.liquidity_token.clone())?;
+    let target_rate = config
+        .resolve_target_rate(deps, env.block.time.seconds())
+        .unwrap_or(config.target_rate);
+
+    let infos = &config.pair_info.asset_infos;
+    let mut cumulative_prices = Vec::with_capacity(infos.len() * (infos.len().saturating_sub(1)));
+    for (i, offer) in infos.iter().enumerate() {
+        for (j, ask) in infos.iter().enumerate() {
+            if i == j {
+                continue;
+            }
+            // Marginal price of one `offer` unit in `ask` terms under the rate-scaled invariant.
+            let price = simulate_unit_price(&config, &pools, i, j, target_rate)?;
+            cumulative_prices.push((offer.clone(), ask.clone(), price));
+        }
+    }
+
+    let assets: Vec<Asset> = infos
+        .iter()
+        .zip(&pools)
+        .map(|(info, amount)| Asset {
+            info: info.clone(),
+            amount: *amount,
+        })
+        .collect();
+
+    Ok(CumulativePricesResponse {
+        assets,
+        total_share,
+        cumulative_prices,
+    })
+}
+
+/// The return for swapping a single unit of asset `offer_idx` into asset `ask_idx`, used as the
+/// spot price sample reported by [`query_cumulative_prices`].
+fn simulate_unit_price(
+    config: &Config,
+    pools: &[Uint128],
+    offer_idx: usize,
+    ask_idx: usize,
+    target_rate: Decimal,
+) -> Result<Uint128, ContractError> {
+    let (return_amount, _, _) = compute_swap(
+        config,
+        pools,
+        offer_idx,
+        ask_idx,
+        Uint128::one(),
+        target_rate,
+        Decimal::zero(),
+    )?;
+    Ok(return_amount)
+}
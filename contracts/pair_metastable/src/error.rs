@@ -0,0 +1,66 @@
+use cosmwasm_std::{OverflowError, StdError};
+use thiserror::Error;
+
+/// This enum describes pair contract errors
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Operation non supported")]
+    NonSupported {},
+
+    #[error("Event of zero transfer")]
+    InvalidZeroAmount {},
+
+    #[error("Operation exceeds max spread limit")]
+    MaxSpreadAssertion {},
+
+    #[error("Provided spread amount exceeds allowed limit")]
+    AllowedSpreadAssertion {},
+
+    #[error("Operation exceeds max splippage tolerance")]
+    MaxSlippageAssertion {},
+
+    #[error("Doubling assets in asset infos")]
+    DoublingAssets {},
+
+    #[error("Asset mismatch between the requested and the stored asset in contract")]
+    AssetMismatch {},
+
+    #[error("Pair type mismatch. Check factory pair configs")]
+    PairTypeMismatch {},
+
+    #[error("The hub exchange rate query returned an invalid value")]
+    InvalidExchangeRate {},
+
+    #[error("Referral commission {commission} exceeds the configured maximum {max}")]
+    ReferralCommissionTooHigh { commission: String, max: String },
+
+    #[error("A referral_address is required when referral_commission is set")]
+    ReferralAddressRequired {},
+
+    #[error("Swaps are currently frozen for this pair")]
+    SwapsFrozen {},
+
+    #[error("Liquidity provision is currently frozen for this pair")]
+    ProvideFrozen {},
+
+    #[error("Liquidity withdrawals are currently frozen for this pair")]
+    WithdrawFrozen {},
+
+    #[error("The required offer amount {required} exceeds the maximum {max}")]
+    MaxOfferAmountAssertion { required: String, max: String },
+
+    #[error("Pool contains between 2 and {max} assets, got {provided}")]
+    InvalidNumberOfAssets { max: usize, provided: usize },
+}
+
+impl From<OverflowError> for ContractError {
+    fn from(o: OverflowError) -> Self {
+        StdError::from(o).into()
+    }
+}
@@ -0,0 +1,6 @@
+pub mod contract;
+pub mod error;
+pub mod math;
+pub mod state;
+
+pub use crate::error::ContractError;
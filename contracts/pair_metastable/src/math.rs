@@ -0,0 +1,115 @@
+use cosmwasm_std::{Decimal256, StdError, StdResult, Uint256};
+
+/// The maximum number of Newton iterations before we give up on convergence.
+const ITERATIONS: u8 = 64;
+
+/// Scales a raw balance by its `target_rate`, returning the rate-adjusted balance `x' = x * rate`.
+///
+/// The LUNA side always uses a rate of one; stLUNA uses the current hub exchange rate so the
+/// invariant prices the two derivatives near their true redemption ratio instead of 1:1.
+pub fn scale(balance: Uint256, rate: Decimal256) -> StdResult<Uint256> {
+    balance
+        .checked_mul(rate.atomics())
+        .map(|v| v / Uint256::from(10u128).pow(Decimal256::DECIMAL_PLACES))
+        .map_err(StdError::overflow)
+}
+
+/// Divides a rate-adjusted balance back out by its `target_rate`, returning the untranslated amount.
+pub fn unscale(scaled: Uint256, rate: Decimal256) -> StdResult<Uint256> {
+    let denom = rate.atomics();
+    if denom.is_zero() {
+        return Err(StdError::generic_err("target rate must be non-zero"));
+    }
+    scaled
+        .checked_mul(Uint256::from(10u128).pow(Decimal256::DECIMAL_PLACES))
+        .map(|v| v / denom)
+        .map_err(StdError::overflow)
+}
+
+/// Computes the stableswap invariant `D` by Newton iteration over the rate-scaled balances.
+///
+/// `D` solves `A·n^n·S + D = A·D·n^n + D^(n+1)/(n^n·∏x')` where `S = ∑x'` and `A` is the
+/// amplification coefficient. `xs` must already be scaled by the per-asset `target_rate`.
+pub fn compute_d(amp: Uint256, xs: &[Uint256]) -> StdResult<Uint256> {
+    let n = Uint256::from(xs.len() as u128);
+    let sum: Uint256 = xs.iter().try_fold(Uint256::zero(), |acc, x| acc.checked_add(*x))?;
+    if sum.is_zero() {
+        return Ok(Uint256::zero());
+    }
+    let ann = amp.checked_mul(n.checked_pow(xs.len() as u32)?)?;
+
+    let mut d = sum;
+    for _ in 0..ITERATIONS {
+        // d_p = D^(n+1) / (n^n · ∏x')
+        let mut d_p = d;
+        for x in xs {
+            d_p = d_p
+                .checked_mul(d)?
+                .checked_div(x.checked_mul(n)?)
+                .map_err(StdError::divide_by_zero)?;
+        }
+        let d_prev = d;
+        let numerator = ann.checked_mul(sum)?.checked_add(d_p.checked_mul(n)?)?.checked_mul(d)?;
+        let denominator = ann
+            .checked_sub(Uint256::one())?
+            .checked_mul(d)?
+            .checked_add(n.checked_add(Uint256::one())?.checked_mul(d_p)?)?;
+        d = numerator.checked_div(denominator).map_err(StdError::divide_by_zero)?;
+
+        if d >= d_prev {
+            if d - d_prev <= Uint256::one() {
+                return Ok(d);
+            }
+        } else if d_prev - d <= Uint256::one() {
+            return Ok(d);
+        }
+    }
+
+    Err(StdError::generic_err("D did not converge"))
+}
+
+/// Solves the quadratic for the new rate-scaled output balance `y'` of asset `out`, given that
+/// the rate-scaled balance of asset `in` was moved to `new_in`.
+///
+/// The remaining balances are held fixed. Returns `y'` in scaled units; callers must `unscale`
+/// it by the output asset's `target_rate` to recover the untranslated token amount.
+pub fn compute_y(amp: Uint256, xs: &[Uint256], new_in: Uint256, in_idx: usize, out_idx: usize) -> StdResult<Uint256> {
+    let d = compute_d(amp, xs)?;
+    let n = Uint256::from(xs.len() as u128);
+    let ann = amp.checked_mul(n.checked_pow(xs.len() as u32)?)?;
+
+    // c = D^(n+1) / (n^n · ∏_{k≠out} x_k · Ann) and b = S + D/Ann, with x_in replaced by new_in.
+    let mut c = d;
+    let mut s = Uint256::zero();
+    for (i, x) in xs.iter().enumerate() {
+        if i == out_idx {
+            continue;
+        }
+        let x_i = if i == in_idx { new_in } else { *x };
+        s = s.checked_add(x_i)?;
+        c = c.checked_mul(d)?.checked_div(x_i.checked_mul(n)?).map_err(StdError::divide_by_zero)?;
+    }
+    c = c.checked_mul(d)?.checked_div(ann.checked_mul(n)?).map_err(StdError::divide_by_zero)?;
+    let b = s.checked_add(d.checked_div(ann).map_err(StdError::divide_by_zero)?)?;
+
+    let mut y = d;
+    for _ in 0..ITERATIONS {
+        let y_prev = y;
+        let numerator = y.checked_mul(y)?.checked_add(c)?;
+        let denominator = y
+            .checked_mul(Uint256::from(2u128))?
+            .checked_add(b)?
+            .checked_sub(d)?;
+        y = numerator.checked_div(denominator).map_err(StdError::divide_by_zero)?;
+
+        if y >= y_prev {
+            if y - y_prev <= Uint256::one() {
+                return Ok(y);
+            }
+        } else if y_prev - y <= Uint256::one() {
+            return Ok(y);
+        }
+    }
+
+    Err(StdError::generic_err("y did not converge"))
+}
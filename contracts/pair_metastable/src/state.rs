@@ -0,0 +1,114 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    to_json_binary, Addr, Decimal, Deps, QueryRequest, StdResult, WasmQuery,
+};
+use cw_storage_plus::Item;
+
+use astroport::asset::PairInfo;
+
+/// This structure stores the main pair configuration for the metastable Lido pool.
+#[cw_serde]
+pub struct Config {
+    /// General pair information (assets and LP token address)
+    pub pair_info: PairInfo,
+    /// The factory contract address
+    pub factory_addr: Addr,
+    /// The pair owner, allowed to toggle the circuit-breaker flags
+    pub owner: Addr,
+    /// Reported in `ConfigResponse`; set to zero at instantiation and not updated by this contract
+    pub block_time_last: u64,
+    /// The Lido hub contract queried for the stLUNA->LUNA exchange rate
+    pub hub_address: Addr,
+    /// The stLUNA token address
+    pub stluna_address: Addr,
+    /// The bLUNA token address
+    pub bluna_address: Addr,
+    /// The time in seconds for which a cached hub exchange rate stays valid before it is re-queried
+    pub ma_half_time: u64,
+    /// The last stLUNA->LUNA exchange rate queried from the hub, used as the stLUNA `target_rate`
+    pub target_rate: Decimal,
+    /// The block time at which `target_rate` was last refreshed from the hub
+    pub target_rate_last: u64,
+    /// The maximum commission fraction a swap may route to a referral address
+    pub max_referral_commission: Decimal,
+    /// Whether swaps are currently halted for this pair
+    pub swaps_frozen: bool,
+    /// Whether liquidity provision is currently halted for this pair
+    pub provide_frozen: bool,
+    /// Whether liquidity withdrawals are currently halted for this pair
+    pub withdraw_frozen: bool,
+    /// Optional passive-concentrated mode parameters and oracle state
+    pub concentrated: Option<ConcentratedState>,
+}
+
+/// Runtime state for the optional passive-concentrated mode, seeded from [`ConcentratedPoolParams`].
+#[cw_serde]
+pub struct ConcentratedState {
+    /// The amplification coefficient concentrating the invariant around `price_scale`
+    pub amp: Decimal,
+    /// The curve steepness parameter
+    pub gamma: Decimal,
+    /// The fee charged when the pool is perfectly balanced
+    pub mid_fee: Decimal,
+    /// The imbalanced-pool fee; at full imbalance the charged fee is
+    /// `(fee_gamma·mid_fee + out_fee) / (fee_gamma + 1)`, which equals `out_fee` when `fee_gamma` is zero
+    pub out_fee: Decimal,
+    /// Controls how quickly the fee interpolates from `mid_fee` to `out_fee` with imbalance
+    pub fee_gamma: Decimal,
+    /// The minimum realized xcp-profit gain before `price_scale` is repegged toward the oracle
+    pub repeg_profit_threshold: Decimal,
+    /// The half-life in seconds of the EMA oracle price
+    pub ma_half_time: u64,
+    /// The current EMA oracle price scale the invariant is concentrated around
+    pub price_scale: Decimal,
+    /// The accumulated xcp-profit tracked for repeg decisions
+    pub xcp_profit: Decimal,
+    /// The block time of the last EMA update
+    pub last_update: u64,
+}
+
+/// Stores the pair configuration at the given key.
+pub const CONFIG: Item<Config> = Item::new("config");
+
+/// The message the Lido hub answers with the current stLUNA->LUNA exchange rate.
+#[cw_serde]
+pub enum HubQueryMsg {
+    /// Returns the hub [`StateResponse`] carrying the current exchange rate
+    State {},
+}
+
+/// The subset of the hub state we rely on.
+#[cw_serde]
+pub struct StateResponse {
+    /// The current stLUNA->LUNA exchange rate
+    pub stluna_exchange_rate: Decimal,
+}
+
+impl Config {
+    /// Returns the stLUNA `target_rate`, querying the hub only when the cached rate is older than
+    /// `ma_half_time`. The LUNA and bLUNA sides always price at a rate of one.
+    pub fn resolve_target_rate(&self, deps: Deps, block_time: u64) -> StdResult<Decimal> {
+        if block_time < self.target_rate_last + self.ma_half_time && !self.target_rate.is_zero() {
+            return Ok(self.target_rate);
+        }
+        self.query_hub_rate(deps)
+    }
+
+    /// Queries the Lido hub for the current stLUNA->LUNA exchange rate.
+    pub fn query_hub_rate(&self, deps: Deps) -> StdResult<Decimal> {
+        let res: StateResponse = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: self.hub_address.to_string(),
+            msg: to_json_binary(&HubQueryMsg::State {})?,
+        }))?;
+        Ok(res.stluna_exchange_rate)
+    }
+
+    /// Returns the per-asset `target_rate` for the asset at `index` in `pair_info.asset_infos`.
+    /// stLUNA scales by the hub rate; every other asset scales by one.
+    pub fn target_rate_for(&self, index: usize, target_rate: Decimal) -> Decimal {
+        match self.pair_info.asset_infos.get(index) {
+            Some(info) if info.to_string() == self.stluna_address.to_string() => target_rate,
+            _ => Decimal::one(),
+        }
+    }
+}
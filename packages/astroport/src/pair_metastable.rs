@@ -10,6 +10,19 @@ pub const DEFAULT_SLIPPAGE: &str = "0.005";
 /// The maximum allowed swap slippage
 pub const MAX_ALLOWED_SLIPPAGE: &str = "0.5";
 
+/// This structure describes the parameters used for creating a metastable pair contract.
+#[cw_serde]
+pub struct InstantiateMsg {
+    /// Information about the assets in the pool
+    pub asset_infos: Vec<AssetInfo>,
+    /// The token contract code ID used for the LP token
+    pub token_code_id: u64,
+    /// The factory contract address
+    pub factory_addr: String,
+    /// The [`LidoPoolParams`] serialized as a binary blob
+    pub init_params: Binary,
+}
+
 /// This structure describes the execute messages available in the contract.
 #[cw_serde]
 pub enum ExecuteMsg {
@@ -18,12 +31,10 @@ pub enum ExecuteMsg {
     Receive(Cw20ReceiveMsg),
     /// ProvideLiquidity allows an account to provide liquidity in a pool with bLUNA
     ProvideLiquidity {
-        /// The two assets available in the pool
-        assets: [Asset; 2],
+        /// The assets available in the pool (any subset of up to N pool assets)
+        assets: Vec<Asset>,
         /// The slippage tolerance that allows liquidity provision only if the price in the pool doesn't move too much
         slippage_tolerance: Option<Decimal>,
-        /// Determines whether the LP tokens minted for the user is auto_staked in the Generator contract
-        auto_stake: Option<bool>,
         /// The receiver of LP tokens
         receiver: Option<String>,
     },
@@ -33,9 +44,34 @@ pub enum ExecuteMsg {
         belief_price: Option<Decimal>,
         max_spread: Option<Decimal>,
         to: Option<String>,
+        /// The address that receives a cut of the commission for routing the swap
+        referral_address: Option<String>,
+        /// The commission slice routed to the referrer, capped by the config max
+        referral_commission: Option<Decimal>,
+    },
+    /// SwapExactAskAmount swaps for an exact ask amount, pulling at most `max_offer_amount`
+    /// of the offer asset and refunding any unused offer back to the sender
+    SwapExactAskAmount {
+        /// The asset and exact amount to receive from the swap
+        ask_asset: Asset,
+        /// The maximum amount of the offer asset the swap may consume
+        max_offer_amount: Uint128,
+        /// The receiver of the ask assets
+        to: Option<String>,
     },
     /// Update the pair configuration
     UpdateConfig { params: Binary },
+    /// UpdatePairStatus enables or disables the whole pair at once (owner only)
+    UpdatePairStatus { is_disabled: bool },
+    /// UpdatePairFreeze independently freezes swaps, liquidity provision and withdrawals (owner only)
+    UpdatePairFreeze {
+        /// Halt swaps when set
+        freeze_swaps: Option<bool>,
+        /// Halt liquidity provision when set
+        freeze_provide: Option<bool>,
+        /// Halt liquidity withdrawals when set
+        freeze_withdraw: Option<bool>,
+    },
 }
 
 /// This structure describes a CW20 hook message.
@@ -46,6 +82,19 @@ pub enum Cw20HookMsg {
         belief_price: Option<Decimal>,
         max_spread: Option<Decimal>,
         to: Option<String>,
+        /// The address that receives a cut of the commission for routing the swap
+        referral_address: Option<String>,
+        /// The commission slice routed to the referrer, capped by the config max
+        referral_commission: Option<Decimal>,
+    },
+    /// SwapExactAskAmount swaps the received amount for an exact ask amount, refunding any unused offer
+    SwapExactAskAmount {
+        /// The asset and exact amount to receive from the swap
+        ask_asset: Asset,
+        /// The maximum amount of the offer asset the swap may consume
+        max_offer_amount: Uint128,
+        /// The receiver of the ask assets
+        to: Option<String>,
     },
     /// Withdraw liquidity from the pool
     WithdrawLiquidity {},
@@ -69,10 +118,16 @@ pub enum QueryMsg {
     Share { amount: Uint128 },
     /// Returns information about a swap simulation in a [`super::pair::SimulationResponse`] object.
     #[returns(SimulationResponse)]
-    Simulation { offer_asset: Asset },
+    Simulation {
+        offer_asset: Asset,
+        referral_commission: Option<Decimal>,
+    },
     /// Returns information about a reverse simulation in a [`super::pair::ReverseSimulationResponse`] object.
     #[returns(ReverseSimulationResponse)]
-    ReverseSimulation { ask_asset: Asset },
+    ReverseSimulation {
+        ask_asset: Asset,
+        referral_commission: Option<Decimal>,
+    },
     /// Returns information about cumulative prices (used for TWAPs) in a [`super::pair::CumulativePricesResponse`] object.
     #[returns(CumulativePricesResponse)]
     CumulativePrices {},
@@ -85,6 +140,28 @@ pub struct LidoPoolParams {
     pub hub_address: String,
     pub stluna_addr: String,
     pub bluna_addr: String,
+    /// The time in seconds for which a cached hub exchange rate stays valid before it is re-queried
+    pub ma_half_time: u64,
+}
+
+/// This struct is used to configure the optional passive-concentrated mode through `UpdateConfig`.
+#[cw_serde]
+pub struct ConcentratedPoolParams {
+    /// The amplification coefficient concentrating the invariant around `price_scale`
+    pub amp: Decimal,
+    /// The curve steepness parameter
+    pub gamma: Decimal,
+    /// The fee charged when the pool is perfectly balanced
+    pub mid_fee: Decimal,
+    /// The imbalanced-pool fee; at full imbalance the charged fee is
+    /// `(fee_gamma·mid_fee + out_fee) / (fee_gamma + 1)`, which equals `out_fee` when `fee_gamma` is zero
+    pub out_fee: Decimal,
+    /// Controls how quickly the fee interpolates from `mid_fee` to `out_fee` with imbalance
+    pub fee_gamma: Decimal,
+    /// The minimum realized xcp-profit gain required before `price_scale` is repegged toward the oracle
+    pub repeg_profit_threshold: Decimal,
+    /// The half-life in seconds of the EMA oracle price
+    pub ma_half_time: u64,
 }
 
 /// This struct is used to return a query result with the total amount of LP tokens and assets in a specific pool.
@@ -116,6 +193,23 @@ pub struct ConfigResponse {
     pub stluna_address: Addr,
     pub bluna_address: Addr,
     pub block_time_last: u64,
+    /// The last stLUNA->LUNA exchange rate queried from the Lido hub, applied as the stLUNA `target_rate`
+    pub target_rate: Decimal,
+    /// The block time at which the stored rate was last refreshed from the hub; when that rate is
+    /// stale, `target_rate` holds a fresh hub query and is newer than this time
+    pub target_rate_last: u64,
+    /// The maximum commission fraction a swap may route to a referral address
+    pub max_referral_commission: Decimal,
+    /// Whether swaps are currently halted for this pair
+    pub swaps_frozen: bool,
+    /// Whether liquidity provision is currently halted for this pair
+    pub provide_frozen: bool,
+    /// Whether liquidity withdrawals are currently halted for this pair
+    pub withdraw_frozen: bool,
+    /// The current EMA oracle price scale the invariant is concentrated around (concentrated mode)
+    pub price_scale: Decimal,
+    /// The accumulated xcp-profit tracked for repeg decisions (concentrated mode)
+    pub xcp_profit: Decimal,
 }
 
 /// This structure holds the parameters that are returned from a swap simulation response
@@ -127,6 +221,8 @@ pub struct SimulationResponse {
     pub spread_amount: Uint128,
     /// The amount of fees charged by the transaction
     pub commission_amount: Uint128,
+    /// The amount of the commission routed to the referral address
+    pub referral_amount: Uint128,
 }
 
 /// This structure holds the parameters that are returned from a reverse swap simulation response.
@@ -138,6 +234,8 @@ pub struct ReverseSimulationResponse {
     pub spread_amount: Uint128,
     /// The amount of fees charged by the transaction
     pub commission_amount: Uint128,
+    /// The amount of the commission routed to the referral address
+    pub referral_amount: Uint128,
 }
 
 /// This structure is used to return a cumulative prices query response.